@@ -0,0 +1,155 @@
+//! Deterministic weighted selection via Vose's alias method.
+
+use crate::{Backend, Pcg64Mcg, PrngKey, Seed};
+use rand::Rng;
+
+/// Folded into every `AliasTable` draw so sampling a table doesn't alias a key's other
+/// `generate`/`rng` streams; see [`Seed::derive`]'s docs for why this crate domain-separates
+/// this way.
+const XOR: u128 = 0x414C_4941_5300_0000_0000_0000_0000_0001;
+
+/// A precomputed table for drawing one of `N` weighted items in O(1), built from a
+/// weight slice using Vose's alias method.
+///
+/// Zero-weight entries are never returned. A slice of equal weights reduces to uniform
+/// selection.
+#[derive(Debug, Clone)]
+pub struct AliasTable {
+    prob: Vec<f64>,
+    alias: Vec<usize>,
+}
+
+impl AliasTable {
+    /// Build an alias table from a slice of non-negative weights. At least one weight
+    /// must be positive.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `weights` is empty or every weight is zero.
+    pub fn new(weights: &[f64]) -> Self {
+        let n = weights.len();
+        assert!(n > 0, "AliasTable requires at least one weight");
+
+        let total: f64 = weights.iter().sum();
+        assert!(total > 0.0, "AliasTable requires at least one positive weight");
+
+        let mut scaled: Vec<f64> = weights.iter().map(|w| w / total * n as f64).collect();
+
+        let mut small: Vec<usize> = Vec::new();
+        let mut large: Vec<usize> = Vec::new();
+        for (i, &s) in scaled.iter().enumerate() {
+            if s < 1.0 {
+                small.push(i);
+            } else {
+                large.push(i);
+            }
+        }
+
+        let mut prob = vec![0.0; n];
+        let mut alias = vec![0; n];
+
+        // Must not pop from both vecs unconditionally (e.g. via `(small.pop(), large.pop())`
+        // in a single `while let`): that would drain an element from whichever vec is
+        // non-empty even on the iteration where the other is already empty and the loop
+        // is about to exit, silently dropping it from both `prob` and `alias`.
+        while !small.is_empty() && !large.is_empty() {
+            let l = small.pop().expect("checked non-empty above");
+            let g = large.pop().expect("checked non-empty above");
+
+            prob[l] = scaled[l];
+            alias[l] = g;
+
+            scaled[g] -= 1.0 - scaled[l];
+            if scaled[g] < 1.0 {
+                small.push(g);
+            } else {
+                large.push(g);
+            }
+        }
+
+        for i in large.into_iter().chain(small) {
+            prob[i] = 1.0;
+        }
+
+        AliasTable { prob, alias }
+    }
+
+    /// The number of items the table chooses between
+    pub fn len(&self) -> usize {
+        self.prob.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.prob.is_empty()
+    }
+
+    /// Deterministically draws the index of one of this table's weighted items for the
+    /// given `Seed` and key
+    pub fn sample<K: PrngKey>(&self, seed: &Seed, key: &K) -> usize {
+        self.sample_with::<Pcg64Mcg, K>(seed, key)
+    }
+
+    /// As [`AliasTable::sample`], but with an explicit RNG backend
+    pub fn sample_with<B: Backend, K: PrngKey>(&self, seed: &Seed, key: &K) -> usize {
+        let mut rng = B::seed_rng(seed.bytes(), key.key(), XOR);
+        let i = rng.gen_range(0..self.len());
+        let u: f64 = rng.gen();
+        if u < self.prob[i] {
+            i
+        } else {
+            self.alias[i]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Key(u64);
+
+    impl PrngKey for Key {
+        fn key(&self) -> u64 {
+            self.0
+        }
+    }
+
+    #[test]
+    fn same_seed_and_key_returns_same_index() {
+        let table = AliasTable::new(&[1.0, 2.0, 3.0]);
+        let seed = Seed::new_from_str("alias test");
+        let key = Key(0);
+
+        let a = table.sample(&seed, &key);
+        let b = table.sample(&seed, &key);
+
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn zero_weight_entries_are_never_returned() {
+        let table = AliasTable::new(&[1.0, 0.0, 1.0]);
+        let seed = Seed::new_from_str("alias zero weight");
+
+        for i in 0..1000 {
+            let index = table.sample(&seed, &Key(i));
+            assert_ne!(index, 1);
+        }
+    }
+
+    #[test]
+    fn equal_weights_are_roughly_uniform() {
+        let table = AliasTable::new(&[1.0, 1.0, 1.0, 1.0]);
+        let seed = Seed::new_from_str("alias uniform");
+
+        let mut counts = [0u32; 4];
+        for i in 0..4000 {
+            let index = table.sample(&seed, &Key(i));
+            counts[index] += 1;
+        }
+
+        for count in counts {
+            assert!((800..1200).contains(&count), "counts: {counts:?}");
+        }
+    }
+}
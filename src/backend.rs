@@ -0,0 +1,64 @@
+//! Pluggable RNG backends used to turn a [`Seed`](crate::Seed) and key into an actual
+//! random number generator.
+//!
+//! [`Pcg64Mcg`] is the fast default. Enable the `chacha` feature for [`ChaCha`], a
+//! cryptographic backend built on `ChaCha20Rng` for users who need stronger statistical
+//! guarantees than a 128-bit MCG provides.
+
+/// Produces a seeded RNG from the raw 32-byte seed material, a key, and a type's `XOR`
+/// constant. Implementors decide how much of the 32 bytes they use and how they fold in
+/// `key`/`xor`, but must do so via fixed-endian (little-endian) byte conversions, never
+/// native ones, so a given `Seed`+key reproduces the same stream on every platform.
+pub trait Backend {
+    type Rng: rand::RngCore;
+
+    fn seed_rng(seed: &[u8; 32], key: u64, xor: u128) -> Self::Rng;
+}
+
+/// Fast, non-cryptographic default backend. Only uses the first 16 bytes of the seed
+/// material, folding `key` and `xor` into a single 128-bit PCG state.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Pcg64Mcg;
+
+impl Backend for Pcg64Mcg {
+    type Rng = rand_pcg::Pcg64Mcg;
+
+    fn seed_rng(seed: &[u8; 32], key: u64, xor: u128) -> Self::Rng {
+        let mut low = [0u8; 16];
+        low.copy_from_slice(&seed[..16]);
+        let seed = u128::from_le_bytes(low);
+        // rand_pcg::Pcg64Mcg::new sets the lowest bit to 1, so the key cannot overlap with that bit
+        let key = (key as u128) << 64;
+        rand_pcg::Pcg64Mcg::new(seed ^ xor ^ key)
+    }
+}
+
+/// Cryptographic backend built on `ChaCha20Rng`. Uses the full 32 bytes of seed
+/// material, so callers who need reproducible, bias-free streams aren't limited to the
+/// 128 bits `Pcg64Mcg` consumes.
+#[cfg(feature = "chacha")]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct ChaCha;
+
+#[cfg(feature = "chacha")]
+impl Backend for ChaCha {
+    type Rng = rand_chacha::ChaCha20Rng;
+
+    fn seed_rng(seed: &[u8; 32], key: u64, xor: u128) -> Self::Rng {
+        use rand::SeedableRng;
+
+        let mut bytes = *seed;
+
+        let xor = xor.to_le_bytes();
+        for (b, x) in bytes[..16].iter_mut().zip(xor) {
+            *b ^= x;
+        }
+
+        let key = key.to_le_bytes();
+        for (b, k) in bytes[16..24].iter_mut().zip(key) {
+            *b ^= k;
+        }
+
+        rand_chacha::ChaCha20Rng::from_seed(bytes)
+    }
+}
@@ -4,6 +4,15 @@
 
 #![feature(associated_type_defaults)]
 
+mod alias;
+mod backend;
+pub mod seq;
+
+pub use alias::AliasTable;
+pub use backend::{Backend, Pcg64Mcg};
+#[cfg(feature = "chacha")]
+pub use backend::ChaCha;
+
 use rand::{
     distributions::{Distribution, Standard},
     Rng,
@@ -24,6 +33,13 @@ pub trait Generate<T> {
 }
 
 /// Helper trait for generating deterministic pseudorandom values for `PrngKey` keys that implement `Generate<T>`
+///
+/// Every method defaults to the fast [`Pcg64Mcg`] backend. Each has a `_with` sibling
+/// that takes an explicit `B: Backend` (e.g. [`ChaCha`] with the `chacha` feature) for
+/// call sites that need stronger statistical guarantees. Rust does not resolve trait
+/// default type parameters from a method call alone, so the backend can't be an inferred
+/// generic parameter of `Prng` itself; `_with` methods are the mechanism that actually
+/// works at call sites.
 pub trait Prng<K: PrngKey> {
     /// Generates a value for the given `Seed` and key `K`
     fn generate<T>(&self, key: &K) -> T
@@ -31,29 +47,133 @@ pub trait Prng<K: PrngKey> {
         K: Generate<T>,
         <K as Generate<T>>::Distribution: Distribution<T>;
 
-    fn rng<T>(&self, key: &K) -> rand_pcg::Pcg64Mcg
+    /// As [`Prng::generate`], but with an explicit RNG backend
+    fn generate_with<B: Backend, T>(&self, key: &K) -> T
+    where
+        K: Generate<T>,
+        <K as Generate<T>>::Distribution: Distribution<T>;
+
+    fn rng<T>(&self, key: &K) -> <Pcg64Mcg as Backend>::Rng
     where
         K: Generate<T>;
+
+    /// As [`Prng::rng`], but with an explicit RNG backend
+    fn rng_with<B: Backend, T>(&self, key: &K) -> B::Rng
+    where
+        K: Generate<T>;
+
+    /// Generates the `index`-th value of an arbitrarily long deterministic stream for
+    /// `key`, in O(1) without iterating from zero. `generate_indexed(key, 0)` is not the
+    /// same value as `generate(key)`, but walking `generate_iter` from zero reproduces
+    /// the same values as calling `generate_indexed` directly.
+    fn generate_indexed<T>(&self, key: &K, index: u64) -> T
+    where
+        K: Generate<T>,
+        <K as Generate<T>>::Distribution: Distribution<T>;
+
+    /// As [`Prng::generate_indexed`], but with an explicit RNG backend
+    fn generate_indexed_with<B: Backend, T>(&self, key: &K, index: u64) -> T
+    where
+        K: Generate<T>,
+        <K as Generate<T>>::Distribution: Distribution<T>;
+
+    /// A lazy iterator over the same stream as `generate_indexed`, starting at index 0
+    fn generate_iter<'s, T>(&'s self, key: &'s K) -> GenerateIter<'s, K, Pcg64Mcg, T>
+    where
+        K: Generate<T>,
+        <K as Generate<T>>::Distribution: Distribution<T>;
+
+    /// As [`Prng::generate_iter`], but with an explicit RNG backend
+    fn generate_iter_with<'s, B: Backend, T>(&'s self, key: &'s K) -> GenerateIter<'s, K, B, T>
+    where
+        K: Generate<T>,
+        <K as Generate<T>>::Distribution: Distribution<T>;
+}
+
+/// Mixed into `index` so `generate_indexed` streams don't alias `generate`'s or each
+/// other; see [`Seed::derive`]'s docs for why this crate domain-separates this way. Odd,
+/// so multiplication by it is invertible mod 2^128.
+const INDEX_GAMMA: u128 = 0x9E3779B97F4A7C15_BF58476D1CE4E5B9;
+
+/// A lazy, infinite iterator over the deterministic stream produced by
+/// [`Prng::generate_indexed`], starting at index 0. Returned by [`Prng::generate_iter`].
+pub struct GenerateIter<'a, K, B, T> {
+    seed: &'a Seed,
+    key: &'a K,
+    index: u64,
+    backend: std::marker::PhantomData<B>,
+    item: std::marker::PhantomData<T>,
+}
+
+impl<'a, K, B, T> Iterator for GenerateIter<'a, K, B, T>
+where
+    K: PrngKey + Generate<T>,
+    B: Backend,
+    <K as Generate<T>>::Distribution: Distribution<T>,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let value = self.seed.generate_indexed_with::<B, T>(self.key, self.index);
+        self.index += 1;
+        Some(value)
+    }
 }
 
 /// Seed values for procedurally generating deterministic pseudo-random numbers
 #[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-pub struct Seed(pub u128);
+pub struct Seed([u8; 32]);
 
 impl Seed {
     /// Generate a `Seed` by hashing an input `&str`
     pub fn new_from_str(seed: &str) -> Self {
-        let hash = &blake3::hash(seed.as_bytes());
-        let bytes = std::array::from_fn(|i| hash.as_bytes()[i]);
-        let u128 = u128::from_ne_bytes(bytes);
-        Seed(u128)
+        let hash = blake3::hash(seed.as_bytes());
+        Seed(*hash.as_bytes())
+    }
+
+    /// The raw 32 bytes of seed material, for backends and subsystems built on top of `Prng`
+    pub(crate) fn bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    /// Derives an independent child `Seed` from this `Seed` and `key`. Distinct keys
+    /// (siblings) yield distinct, collision-resistant seeds, and the same key always
+    /// re-derives the same child, so a tree of sub-generators (e.g. galaxy -> system ->
+    /// planet) stays fully reproducible from the root while each level's own
+    /// `generate`/`rng` streams remain independent of the derivation.
+    ///
+    /// This crate's other independent stream sources (`AliasTable` draws, `generate_indexed`)
+    /// use the same trick to stay independent: a distinct tag or constant is mixed in per
+    /// entry point so its stream can never alias another's, even if their other inputs match.
+    pub fn derive<K: PrngKey>(&self, key: &K) -> Seed {
+        self.derive_bytes(DERIVE_DOMAIN_KEY, &key.key().to_le_bytes())
+    }
+
+    /// As [`Seed::derive`], but keyed by an arbitrary string instead of a `PrngKey`.
+    pub fn derive_str(&self, key: &str) -> Seed {
+        self.derive_bytes(DERIVE_DOMAIN_STR, key.as_bytes())
+    }
+
+    fn derive_bytes(&self, domain: &[u8], key: &[u8]) -> Seed {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(domain);
+        hasher.update(&self.0);
+        hasher.update(key);
+        Seed(*hasher.finalize().as_bytes())
     }
 }
 
+/// Per-entry-point tags for [`Seed::derive`] and [`Seed::derive_str`]; see `derive`'s docs.
+const DERIVE_DOMAIN_KEY: &[u8] = b"procedural_generation::Seed::derive";
+const DERIVE_DOMAIN_STR: &[u8] = b"procedural_generation::Seed::derive_str";
+
 impl From<u128> for Seed {
     fn from(value: u128) -> Self {
-        Seed(value)
+        let mut bytes = [0u8; 32];
+        // little-endian, like the backends in `backend.rs`, for the same reason
+        bytes[..16].copy_from_slice(&value.to_le_bytes());
+        Seed(bytes)
     }
 }
 
@@ -69,18 +189,75 @@ impl<K: PrngKey> Prng<K> for Seed {
         K: Generate<T>,
         <K as Generate<T>>::Distribution: Distribution<T>,
     {
-        let mut rng = self.rng(key);
+        self.generate_with::<Pcg64Mcg, T>(key)
+    }
+
+    fn generate_with<B: Backend, T>(&self, key: &K) -> T
+    where
+        K: Generate<T>,
+        <K as Generate<T>>::Distribution: Distribution<T>,
+    {
+        let mut rng = self.rng_with::<B, T>(key);
+        K::distribution().sample(&mut rng)
+    }
+
+    fn rng<T>(&self, key: &K) -> <Pcg64Mcg as Backend>::Rng
+    where
+        K: Generate<T>,
+    {
+        self.rng_with::<Pcg64Mcg, T>(key)
+    }
+
+    fn rng_with<B: Backend, T>(&self, key: &K) -> B::Rng
+    where
+        K: Generate<T>,
+    {
+        B::seed_rng(&self.0, key.key(), K::XOR)
+    }
+
+    fn generate_indexed<T>(&self, key: &K, index: u64) -> T
+    where
+        K: Generate<T>,
+        <K as Generate<T>>::Distribution: Distribution<T>,
+    {
+        self.generate_indexed_with::<Pcg64Mcg, T>(key, index)
+    }
+
+    fn generate_indexed_with<B: Backend, T>(&self, key: &K, index: u64) -> T
+    where
+        K: Generate<T>,
+        <K as Generate<T>>::Distribution: Distribution<T>,
+    {
+        // Fold `index` into the 128-bit `xor` material instead of the 64-bit `key`: the
+        // domain (2^64 possible indices) is smaller than the codomain (2^128), so an
+        // invertible-mod-2^128 map can stay injective over the whole domain with no
+        // output forced to collide, unlike mixing into `key` where every output had to
+        // be used and one had to be special-cased away from zero.
+        let mixed = (index as u128 + 1).wrapping_mul(INDEX_GAMMA);
+        let mut rng = B::seed_rng(&self.0, key.key(), K::XOR ^ mixed);
         K::distribution().sample(&mut rng)
     }
 
-    fn rng<T>(&self, key: &K) -> rand_pcg::Pcg64Mcg
+    fn generate_iter<'s, T>(&'s self, key: &'s K) -> GenerateIter<'s, K, Pcg64Mcg, T>
     where
         K: Generate<T>,
+        <K as Generate<T>>::Distribution: Distribution<T>,
     {
-        // rand_pcg::Pcg64Mcg::new sets the lowest bit to 1, so the key cannot overlap with that bit
-        let key = (key.key() as u128) << 64;
-        let rng_seed = self.0 ^ K::XOR ^ key;
-        rand_pcg::Pcg64Mcg::new(rng_seed)
+        self.generate_iter_with::<Pcg64Mcg, T>(key)
+    }
+
+    fn generate_iter_with<'s, B: Backend, T>(&'s self, key: &'s K) -> GenerateIter<'s, K, B, T>
+    where
+        K: Generate<T>,
+        <K as Generate<T>>::Distribution: Distribution<T>,
+    {
+        GenerateIter {
+            seed: self,
+            key,
+            index: 0,
+            backend: std::marker::PhantomData,
+            item: std::marker::PhantomData,
+        }
     }
 }
 
@@ -199,6 +376,140 @@ mod tests {
         assert_ne!(seed.generate::<Value1>(&k1), seed.generate::<Value1>(&k2));
     }
 
+    #[test]
+    fn generate_indexed_matches_generate_iter() {
+        let seed = Seed::new_from_str("indexed test");
+        let key = ValueKey::new(7);
+
+        let indexed: Vec<Value1> = (0..5).map(|i| seed.generate_indexed(&key, i)).collect();
+        let iterated: Vec<Value1> = seed.generate_iter::<Value1>(&key).take(5).collect();
+
+        assert_eq!(indexed, iterated);
+    }
+
+    #[test]
+    fn generate_indexed_zero_is_independent_of_generate_of_a_different_type() {
+        let seed = Seed::new_from_str("indexed independence test");
+        let key = ValueKey::new(7);
+
+        let indexed = seed.generate_indexed::<Value1>(&key, 0);
+        let generated = seed.generate::<Value2>(&key);
+
+        assert_ne!(indexed, generated);
+    }
+
+    #[test]
+    fn generate_indexed_zero_is_independent_of_generate_of_the_same_type() {
+        let seed = Seed::new_from_str("indexed same-type independence test");
+        let key = ValueKey::new(7);
+
+        let indexed = seed.generate_indexed::<Value1>(&key, 0);
+        let generated = seed.generate::<Value1>(&key);
+
+        assert_ne!(indexed, generated);
+    }
+
+    #[test]
+    fn generate_indexed_at_u64_max_is_independent_of_generate_of_the_same_type() {
+        let seed = Seed::new_from_str("indexed boundary independence test");
+        let key = ValueKey::new(7);
+
+        let indexed = seed.generate_indexed::<Value1>(&key, u64::MAX);
+        let generated = seed.generate::<Value1>(&key);
+
+        assert_ne!(indexed, generated);
+    }
+
+    #[test]
+    fn different_indices_return_different_values() {
+        let seed = Seed::new_from_str("indexed distinctness test");
+        let key = ValueKey::new(7);
+
+        let value0 = seed.generate_indexed::<Value1>(&key, 0);
+        let value1 = seed.generate_indexed::<Value1>(&key, 1);
+
+        assert_ne!(value0, value1);
+    }
+
+    #[test]
+    fn generate_indexed_does_not_collide_across_parity_classes() {
+        // A previous mixing formula OR'ed a bit into every index to dodge the
+        // `index == u64::MAX` collision, which collapsed this index onto index 0 instead.
+        let seed = Seed::new_from_str("indexed parity collision test");
+        let key = ValueKey::new(7);
+
+        let value0 = seed.generate_indexed::<Value1>(&key, 0);
+        let other = seed.generate_indexed::<Value1>(&key, 1018231460777725123);
+
+        assert_ne!(value0, other);
+    }
+
+    #[test]
+    fn generate_indexed_u64_max_does_not_collide_with_the_index_that_used_to_be_remapped_to_it() {
+        // A previous mixing formula special-cased `index == u64::MAX` (the one input that
+        // mapped to zero under the 64-bit bijection) to the output `1` instead, which made
+        // it collide with whichever index naturally produced `1`.
+        let seed = Seed::new_from_str("indexed boundary remap collision test");
+        let key = ValueKey::new(7);
+
+        let boundary = seed.generate_indexed::<Value1>(&key, u64::MAX);
+        let formerly_remapped_to = seed.generate_indexed::<Value1>(&key, 17428512612931826492);
+
+        assert_ne!(boundary, formerly_remapped_to);
+    }
+
+    #[test]
+    fn derive_is_deterministic() {
+        let seed = Seed::new_from_str("derive test");
+        let key = ValueKey::new(7);
+
+        assert_eq!(seed.derive(&key), seed.derive(&key));
+    }
+
+    #[test]
+    fn derive_distinguishes_sibling_keys() {
+        let seed = Seed::new_from_str("derive sibling test");
+
+        let a = seed.derive(&ValueKey::new(0));
+        let b = seed.derive(&ValueKey::new(1));
+
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn derive_distinguishes_key_paths() {
+        let seed = Seed::new_from_str("derive path test");
+
+        let galaxy = seed.derive(&ValueKey::new(1));
+        let system = galaxy.derive(&ValueKey::new(2));
+        let other_system = seed.derive(&ValueKey::new(2));
+
+        assert_ne!(system, other_system);
+        assert_ne!(system, galaxy);
+    }
+
+    #[test]
+    fn derive_str_is_deterministic() {
+        let seed = Seed::new_from_str("derive str test");
+
+        assert_eq!(seed.derive_str("region"), seed.derive_str("region"));
+        assert_ne!(seed.derive_str("region"), seed.derive_str("other region"));
+    }
+
+    #[test]
+    fn derive_and_derive_str_never_alias_even_with_matching_raw_key_bytes() {
+        let seed = Seed::new_from_str("derive cross-api test");
+
+        let key = ValueKey::new(0);
+        let matching_bytes = "\0\0\0\0\0\0\0\0";
+        assert_eq!(key.key().to_le_bytes(), matching_bytes.as_bytes());
+
+        let from_key = seed.derive(&key);
+        let from_str = seed.derive_str(matching_bytes);
+
+        assert_ne!(from_key, from_str);
+    }
+
     #[test]
     fn prng_rng_and_generate() {
         let seed = Seed::new_from_str("rng and generate");
@@ -208,4 +519,64 @@ mod tests {
         let generate_value = seed.generate::<Value1>(&key);
         assert_eq!(rng_value, generate_value);
     }
+
+    #[cfg(feature = "chacha")]
+    #[test]
+    fn chacha_same_seed_and_key_returns_same_stream() {
+        let seed = Seed::new_from_str("chacha determinism test");
+        let key = ValueKey::new(7);
+
+        let a = seed.generate_with::<ChaCha, Value1>(&key);
+        let b = seed.generate_with::<ChaCha, Value1>(&key);
+
+        assert_eq!(a, b);
+    }
+
+    #[cfg(feature = "chacha")]
+    #[test]
+    fn chacha_differs_from_the_default_backend() {
+        let seed = Seed::new_from_str("chacha distinctness test");
+        let key = ValueKey::new(7);
+
+        let chacha = seed.generate_with::<ChaCha, Value1>(&key);
+        let pcg = seed.generate::<Value1>(&key);
+
+        assert_ne!(chacha, pcg);
+    }
+
+    #[cfg(feature = "chacha")]
+    #[test]
+    fn chacha_different_keys_return_different_values() {
+        let seed = Seed::new_from_str("chacha key distinctness test");
+        let k1 = ValueKey::new(1);
+        let k2 = ValueKey::new(2);
+
+        assert_ne!(
+            seed.generate_with::<ChaCha, Value1>(&k1),
+            seed.generate_with::<ChaCha, Value1>(&k2)
+        );
+    }
+
+    #[cfg(feature = "chacha")]
+    #[test]
+    fn chacha_different_indices_return_different_values() {
+        let seed = Seed::new_from_str("chacha indexed distinctness test");
+        let key = ValueKey::new(7);
+
+        let value0 = seed.generate_indexed_with::<ChaCha, Value1>(&key, 0);
+        let value1 = seed.generate_indexed_with::<ChaCha, Value1>(&key, 1);
+
+        assert_ne!(value0, value1);
+    }
+
+    #[cfg(feature = "chacha")]
+    #[test]
+    fn chacha_rng_and_generate_agree() {
+        let seed = Seed::new_from_str("chacha rng and generate");
+        let key = ValueKey(23);
+        let mut rng = seed.rng_with::<ChaCha, Value1>(&key);
+        let rng_value = rng.gen::<Value1>();
+        let generate_value = seed.generate_with::<ChaCha, Value1>(&key);
+        assert_eq!(rng_value, generate_value);
+    }
 }
@@ -0,0 +1,150 @@
+//! Keyed shuffling and sampling-without-replacement, mirroring `rand::seq` but driven by
+//! a `Seed` and `PrngKey` so the result is reproducible across runs and platforms.
+
+use crate::{Backend, Pcg64Mcg, PrngKey, Seed};
+use rand::Rng;
+use std::collections::HashSet;
+
+const SHUFFLE_XOR: u128 = 0x5345_5100_0000_0000_0000_0000_0000_0001;
+
+/// Deterministically shuffles `slice` in place for the given `Seed` and key, using a
+/// Fisher-Yates shuffle.
+pub fn shuffle<T, K: PrngKey>(seed: &Seed, key: &K, slice: &mut [T]) {
+    shuffle_with::<Pcg64Mcg, T, K>(seed, key, slice)
+}
+
+/// As [`shuffle`], but with an explicit RNG backend
+pub fn shuffle_with<B: Backend, T, K: PrngKey>(seed: &Seed, key: &K, slice: &mut [T]) {
+    let mut rng = B::seed_rng(seed.bytes(), key.key(), SHUFFLE_XOR);
+    for i in (1..slice.len()).rev() {
+        let j = rng.gen_range(0..=i);
+        slice.swap(i, j);
+    }
+}
+
+/// Index sampling, mirroring `rand::seq::index`.
+pub mod index {
+    use super::*;
+
+    const SAMPLE_XOR: u128 = 0x5345_5100_0000_0000_0000_0000_0000_0002;
+
+    /// Deterministically chooses `amount` distinct indices in `0..length` for the given
+    /// `Seed` and key. Switches strategy by density: a partial Fisher-Yates over a
+    /// scratch buffer for `amount` close to `length`, and a Floyd-style set-insertion
+    /// otherwise to keep memory bounded.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `amount > length`.
+    pub fn sample<K: PrngKey>(seed: &Seed, key: &K, length: usize, amount: usize) -> Vec<usize> {
+        sample_with::<Pcg64Mcg, K>(seed, key, length, amount)
+    }
+
+    /// As [`sample`], but with an explicit RNG backend
+    pub fn sample_with<B: Backend, K: PrngKey>(
+        seed: &Seed,
+        key: &K,
+        length: usize,
+        amount: usize,
+    ) -> Vec<usize> {
+        assert!(amount <= length, "cannot choose more indices than `length`");
+
+        let mut rng = B::seed_rng(seed.bytes(), key.key(), SAMPLE_XOR);
+
+        // Floyd's algorithm is O(amount) time and space, but its rejection-free variant
+        // degrades as `amount` approaches `length`; switch to a partial Fisher-Yates
+        // (O(length) space, O(amount) swaps) once the two are close.
+        if amount > length / 2 {
+            partial_fisher_yates(&mut rng, length, amount)
+        } else {
+            floyd(&mut rng, length, amount)
+        }
+    }
+
+    fn partial_fisher_yates<R: rand::RngCore>(rng: &mut R, length: usize, amount: usize) -> Vec<usize> {
+        let mut indices: Vec<usize> = (0..length).collect();
+        for i in 0..amount {
+            let j = rng.gen_range(i..length);
+            indices.swap(i, j);
+        }
+        indices.truncate(amount);
+        indices
+    }
+
+    fn floyd<R: rand::RngCore>(rng: &mut R, length: usize, amount: usize) -> Vec<usize> {
+        let mut selected = HashSet::with_capacity(amount);
+        let mut result = Vec::with_capacity(amount);
+        for j in (length - amount)..length {
+            let t = rng.gen_range(0..=j);
+            let t = if selected.contains(&t) { j } else { t };
+            selected.insert(t);
+            result.push(t);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Key(u64);
+
+    impl PrngKey for Key {
+        fn key(&self) -> u64 {
+            self.0
+        }
+    }
+
+    #[test]
+    fn same_seed_and_key_shuffles_the_same_way() {
+        let seed = Seed::new_from_str("shuffle test");
+        let key = Key(0);
+
+        let mut a = [1, 2, 3, 4, 5, 6, 7, 8];
+        let mut b = a;
+        shuffle(&seed, &key, &mut a);
+        shuffle(&seed, &key, &mut b);
+
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn shuffle_is_a_permutation() {
+        let seed = Seed::new_from_str("shuffle permutation test");
+        let key = Key(1);
+
+        let mut values = [1, 2, 3, 4, 5, 6, 7, 8];
+        shuffle(&seed, &key, &mut values);
+
+        let mut sorted = values;
+        sorted.sort_unstable();
+        assert_eq!(sorted, [1, 2, 3, 4, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn index_sample_returns_distinct_indices_in_range() {
+        let seed = Seed::new_from_str("index sample test");
+        let key = Key(0);
+
+        for amount in [0, 1, 5, 50, 100] {
+            let indices = index::sample(&seed, &key, 100, amount);
+            assert_eq!(indices.len(), amount);
+
+            let unique: HashSet<_> = indices.iter().copied().collect();
+            assert_eq!(unique.len(), amount);
+            assert!(indices.iter().all(|&i| i < 100));
+        }
+    }
+
+    #[test]
+    fn same_seed_and_key_samples_the_same_indices() {
+        let seed = Seed::new_from_str("index sample determinism test");
+        let key = Key(7);
+
+        let a = index::sample(&seed, &key, 1000, 10);
+        let b = index::sample(&seed, &key, 1000, 10);
+
+        assert_eq!(a, b);
+    }
+}